@@ -0,0 +1,125 @@
+pub const LISTEN_ADDR: &str = "127.0.0.1:8080";
+pub const UPSTREAM_BASE: &str = "http://127.0.0.1:3000";
+
+/// When enabled, every accepted TCP connection is expected to start with a
+/// PROXY protocol v1 or v2 header (as written by an upstream L4 load balancer)
+/// before any HTTP bytes. The decoded source address is used as the client's
+/// real IP instead of the load balancer's own address.
+pub const PROXY_PROTOCOL_ENABLED: bool = false;
+
+/// Optional outbound HTTP proxy to chain the upstream request through, e.g.
+/// `"http://proxy.internal:8080"`. When set, `reqwest` tunnels HTTPS requests
+/// through it with a `CONNECT`, matching a corporate network where the
+/// upstream is only reachable via that tunnel.
+pub const UPSTREAM_HTTP_PROXY: Option<&str> = None;
+
+/// Path to the optional YAML config for the generalized layer-4 router (see the
+/// `upstreams` module). When the file doesn't exist, the router simply isn't
+/// started and only the HTTP reverse proxy above runs.
+pub const ROUTER_CONFIG_PATH: &str = "router.yaml";
+
+/// A layer-4 router config: a set of listeners, each picking an upstream by
+/// TLS SNI hostname (falling back to `default` for plain TCP or an
+/// unrecognized hostname).
+#[derive(Debug, Clone, serde::Deserialize)]
+pub struct RouterConfig {
+    pub listeners: Vec<ListenerConfig>,
+}
+
+#[derive(Debug, Clone, serde::Deserialize)]
+pub struct ListenerConfig {
+    /// Address to bind, e.g. `"0.0.0.0:8443"`.
+    pub bind: String,
+    /// Whether connections on this listener start with a TLS ClientHello and
+    /// should be routed by SNI rather than always using `default`.
+    #[serde(default)]
+    pub tls: bool,
+    /// SNI hostname -> upstream. Ignored when `tls` is false.
+    #[serde(default)]
+    pub sni: std::collections::HashMap<String, Upstream>,
+    pub default: Upstream,
+}
+
+/// What to do with a connection once a listener has picked it.
+#[derive(Debug, Clone, serde::Deserialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum Upstream {
+    /// Close the connection immediately.
+    Ban,
+    /// Loop bytes back to the sender.
+    Echo,
+    /// Dial `addr` and splice the connection bidirectionally.
+    Proxy {
+        addr: String,
+        /// `"tcp"` or `"kcp"` (reliable UDP, for lossy links where TCP's
+        /// head-of-line blocking hurts).
+        #[serde(default = "default_upstream_protocol")]
+        protocol: String,
+        #[serde(default)]
+        kcp: KcpConfig,
+    },
+}
+
+fn default_upstream_protocol() -> String {
+    "tcp".to_string()
+}
+
+/// Tunable knobs for the `kcp` upstream protocol, mirroring `tokio_kcp`'s own
+/// config so the latency/bandwidth trade-off is adjustable per upstream.
+#[derive(Debug, Clone, serde::Deserialize)]
+pub struct KcpConfig {
+    #[serde(default = "default_kcp_nodelay")]
+    pub nodelay: bool,
+    /// Update interval in milliseconds.
+    #[serde(default = "default_kcp_interval")]
+    pub interval: u32,
+    /// Fast-resend trigger: resend after this many out-of-order ACKs.
+    #[serde(default = "default_kcp_resend")]
+    pub resend: u32,
+    /// Flow-control window size, in packets.
+    #[serde(default = "default_kcp_flow_control_window")]
+    pub flow_control_window: u16,
+    #[serde(default = "default_kcp_mtu")]
+    pub mtu: usize,
+}
+
+impl Default for KcpConfig {
+    fn default() -> Self {
+        Self {
+            nodelay: default_kcp_nodelay(),
+            interval: default_kcp_interval(),
+            resend: default_kcp_resend(),
+            flow_control_window: default_kcp_flow_control_window(),
+            mtu: default_kcp_mtu(),
+        }
+    }
+}
+
+fn default_kcp_nodelay() -> bool {
+    true
+}
+
+fn default_kcp_interval() -> u32 {
+    10
+}
+
+fn default_kcp_resend() -> u32 {
+    2
+}
+
+fn default_kcp_flow_control_window() -> u16 {
+    256
+}
+
+fn default_kcp_mtu() -> usize {
+    1400
+}
+
+/// Loads and parses the router config from disk, if present.
+pub fn load_router_config(path: &str) -> Result<Option<RouterConfig>, Box<dyn std::error::Error>> {
+    if !std::path::Path::new(path).exists() {
+        return Ok(None);
+    }
+    let contents = std::fs::read_to_string(path)?;
+    Ok(Some(serde_yaml::from_str(&contents)?))
+}
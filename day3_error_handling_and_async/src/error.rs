@@ -8,6 +8,12 @@ pub enum ProxyError {
     RequestError(#[from] reqwest::Error),
     #[error("Failed to convert body: {0}")]
     BodyConversionError(String),
+    /// The configured `UPSTREAM_HTTP_PROXY` couldn't be parsed, or the upstream
+    /// HTTP client couldn't be built (with or without a proxy configured). An actual failed `CONNECT`
+    /// negotiation with that proxy surfaces as `RequestError` instead, since
+    /// `reqwest` reports it as a plain `reqwest::Error`.
+    #[error("Failed to configure upstream proxy tunnel: {0}")]
+    TunnelConfigError(String),
 }
 
 impl IntoResponse for ProxyError {
@@ -19,6 +25,9 @@ impl IntoResponse for ProxyError {
             ProxyError::RequestError(e) => {
                 (StatusCode::BAD_GATEWAY, format!("Upstream error: {}", e))
             }
+            ProxyError::TunnelConfigError(e) => {
+                (StatusCode::BAD_GATEWAY, format!("Tunnel config error: {}", e))
+            }
         };
         (status, msg).into_response()
     }
@@ -1,35 +1,97 @@
 mod proxy;
 mod config;
 mod error;
+mod proxy_protocol;
+mod upstreams;
 
-use axum::{Router, routing::any};
-use crate::proxy::proxy_handler;
-use crate::config::LISTEN_ADDR;
+use axum::{extract::Extension, Router, routing::any};
+use crate::config::{LISTEN_ADDR, PROXY_PROTOCOL_ENABLED, ROUTER_CONFIG_PATH};
+use crate::proxy::{proxy_handler, ClientAddr};
+use crate::proxy_protocol::read_proxy_protocol_header;
+use hyper_util::rt::{TokioExecutor, TokioIo};
+use hyper_util::server::conn::auto::Builder;
 use std::net::SocketAddr;
+use tower::Service;
 
 #[tokio::main]
 async fn main() -> Result<(), Box<dyn std::error::Error>> {
     println!("📋 Initializing reverse proxy server");
     println!("📋 Upstream server: {}", crate::config::UPSTREAM_BASE);
-    
+
     // Create our application with a route that matches any path
    let app = Router::new()
     .route("/", any(proxy_handler))         // catch `/`
     .route("/*path", any(proxy_handler));
-    
+
     println!("🚀 Reverse Proxy running on http://{LISTEN_ADDR}");
     println!("🔌 Ready to handle requests...");
     let addr: SocketAddr = LISTEN_ADDR.parse()?;
-    
+
     // In Axum 0.7, we use a TcpListener
     let listener = tokio::net::TcpListener::bind(addr).await?;
     println!("📡 Server bound to {}", addr);
-    
-    // Serve the application
+    if PROXY_PROTOCOL_ENABLED {
+        println!("🛡️  PROXY protocol decoding enabled");
+    }
+
+    // Start the config-driven L4 router listeners (Ban/Echo/Proxy, SNI-routed),
+    // if a router config file is present, alongside the HTTP reverse proxy.
+    match crate::config::load_router_config(ROUTER_CONFIG_PATH) {
+        Ok(Some(router_config)) => {
+            for listener_config in router_config.listeners {
+                tokio::spawn(async move {
+                    if let Err(err) = crate::upstreams::run_listener(listener_config).await {
+                        eprintln!("⚠️  L4 router listener failed: {err}");
+                    }
+                });
+            }
+        }
+        Ok(None) => {}
+        Err(err) => eprintln!("⚠️  Failed to load {ROUTER_CONFIG_PATH}: {err}"),
+    }
+
+    // Serve the application with our own accept loop so we can decode a PROXY
+    // protocol header off each connection before any HTTP bytes are read.
     println!("🔄 Starting server loop");
-    axum::serve(listener, app).await?;
-    
-    println!("👋 Server shutting down gracefully");
-    Ok(())
-}
+    loop {
+        let (mut stream, peer_addr) = match listener.accept().await {
+            Ok(accepted) => accepted,
+            Err(err) => {
+                eprintln!("⚠️  Failed to accept connection: {err}");
+                continue;
+            }
+        };
+        let app = app.clone();
+
+        tokio::spawn(async move {
+            let client_addr = if PROXY_PROTOCOL_ENABLED {
+                match read_proxy_protocol_header(&mut stream).await {
+                    Ok(Some(header)) => header.source,
+                    Ok(None) => peer_addr,
+                    Err(err) => {
+                        eprintln!(
+                            "⚠️  Rejecting connection from {peer_addr}: bad PROXY protocol header: {err}"
+                        );
+                        return;
+                    }
+                }
+            } else {
+                peer_addr
+            };
+
+            let io = TokioIo::new(stream);
+            let mut tower_service = app.layer(Extension(ClientAddr(client_addr)));
 
+            let hyper_service = hyper::service::service_fn(move |request| {
+                tower_service.call(request)
+            });
+
+            if let Err(err) = Builder::new(TokioExecutor::new())
+                .serve_connection_with_upgrades(io, hyper_service)
+                .await
+            {
+                eprintln!("⚠️  Error serving connection from {peer_addr}: {err}");
+            }
+        });
+    }
+}
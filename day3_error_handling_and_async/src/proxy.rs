@@ -1,10 +1,67 @@
-use axum::{body::Body, http::Request, response::IntoResponse};
+use axum::{
+    body::Body,
+    extract::Extension,
+    http::{HeaderMap, HeaderName, Request},
+    response::IntoResponse,
+};
 use reqwest::Client;
+use std::net::SocketAddr;
 
-use crate::{config::UPSTREAM_BASE, error::ProxyError};
+use crate::{
+    config::{UPSTREAM_BASE, UPSTREAM_HTTP_PROXY},
+    error::ProxyError,
+};
 
-pub async fn proxy_handler(req: Request<Body>) -> Result<impl IntoResponse, ProxyError> {
-    let client = Client::new();
+/// The real client address for the current connection — either the raw TCP
+/// peer address, or the source address decoded from a PROXY protocol header
+/// when running behind an L4 load balancer. Inserted per-connection in `main.rs`.
+#[derive(Debug, Clone, Copy)]
+pub struct ClientAddr(pub SocketAddr);
+
+/// Hop-by-hop headers that must never be forwarded by a proxy (RFC 2616 §13.5.1).
+const HOP_BY_HOP_HEADERS: &[&str] = &[
+    "connection",
+    "keep-alive",
+    "proxy-authenticate",
+    "proxy-authorization",
+    "te",
+    "trailers",
+    "transfer-encoding",
+    "upgrade",
+];
+
+// Strips the fixed hop-by-hop headers plus any header named as a token in
+// the `Connection` header value, in place.
+fn strip_hop_by_hop_headers(headers: &mut HeaderMap) {
+    let mut to_remove: Vec<String> = HOP_BY_HOP_HEADERS.iter().map(|h| h.to_string()).collect();
+
+    if let Some(connection) = headers.get(axum::http::header::CONNECTION) {
+        if let Ok(value) = connection.to_str() {
+            to_remove.extend(value.split(',').map(|token| token.trim().to_lowercase()));
+        }
+    }
+
+    for name in to_remove {
+        if let Ok(header_name) = HeaderName::try_from(name) {
+            headers.remove(header_name);
+        }
+    }
+}
+
+pub async fn proxy_handler(
+    Extension(ClientAddr(peer_addr)): Extension<ClientAddr>,
+    req: Request<Body>,
+) -> Result<impl IntoResponse, ProxyError> {
+    let mut client_builder = Client::builder();
+    if let Some(proxy_url) = UPSTREAM_HTTP_PROXY {
+        let proxy = reqwest::Proxy::all(proxy_url).map_err(|e| {
+            ProxyError::TunnelConfigError(format!("invalid upstream proxy url: {e}"))
+        })?;
+        client_builder = client_builder.proxy(proxy);
+    }
+    let client = client_builder.build().map_err(|e| {
+        ProxyError::TunnelConfigError(format!("failed to build tunneling client: {e}"))
+    })?;
 
     // Log request details
     println!("📥 Request received: {} {}", req.method(), req.uri());
@@ -22,6 +79,26 @@ pub async fn proxy_handler(req: Request<Body>) -> Result<impl IntoResponse, Prox
     // Extract method before consuming the request
     let method = req.method().clone();
 
+    let mut headers = req.headers().clone();
+    strip_hop_by_hop_headers(&mut headers);
+
+    // Append our peer to X-Forwarded-For and set the other forwarding headers
+    let xff_name = HeaderName::from_static("x-forwarded-for");
+    let xff_value = match headers.get(&xff_name).and_then(|v| v.to_str().ok()) {
+        Some(existing) => format!("{}, {}", existing, peer_addr.ip()),
+        None => peer_addr.ip().to_string(),
+    };
+    if let Ok(value) = xff_value.parse() {
+        headers.insert(xff_name, value);
+    }
+    headers.insert(
+        HeaderName::from_static("x-forwarded-proto"),
+        "http".parse().unwrap(),
+    );
+    if let Some(host) = req.headers().get(axum::http::header::HOST).cloned() {
+        headers.insert(HeaderName::from_static("x-forwarded-host"), host);
+    }
+
     // Convert Body -> bytes -> reqwest body
     let bytes = match axum::body::to_bytes(req.into_body(), 1024 * 1024 * 10).await {
         Ok(bytes) => bytes,
@@ -31,6 +108,7 @@ pub async fn proxy_handler(req: Request<Body>) -> Result<impl IntoResponse, Prox
     // Forward the request to the upstream server
     let resp = client
         .request(method, upstream_uri)
+        .headers(headers)
         .body(bytes)
         .send()
         .await
@@ -38,7 +116,8 @@ pub async fn proxy_handler(req: Request<Body>) -> Result<impl IntoResponse, Prox
 
     // Convert the response to a format that can be returned
     let status = resp.status();
-    let headers = resp.headers().clone();
+    let mut headers = resp.headers().clone();
+    strip_hop_by_hop_headers(&mut headers);
     println!("📤 Response received from upstream: {}", status);
 
     let body = resp.bytes().await.map_err(ProxyError::RequestError)?;
@@ -47,7 +126,7 @@ pub async fn proxy_handler(req: Request<Body>) -> Result<impl IntoResponse, Prox
     // Build and return the response
     let mut response_builder = axum::response::Response::builder().status(status);
 
-    // Add all headers from the upstream response
+    // Add the cleaned headers from the upstream response
     for (name, value) in headers.iter() {
         println!("🧾 Header: {}: {:?}", name, value);
         response_builder = response_builder.header(name, value);
@@ -0,0 +1,193 @@
+use std::io;
+use std::net::{Ipv4Addr, Ipv6Addr, SocketAddr};
+
+use tokio::io::AsyncReadExt;
+use tokio::net::TcpStream;
+
+/// 12-byte magic that prefixes every PROXY protocol v2 header.
+const V2_SIGNATURE: [u8; 12] = [
+    0x0D, 0x0A, 0x0D, 0x0A, 0x00, 0x0D, 0x0A, 0x51, 0x55, 0x49, 0x54, 0x0A,
+];
+
+/// v1 headers are a single line and the spec caps them at 107 bytes including
+/// the trailing `\r\n`.
+const MAX_V1_LINE_LEN: usize = 107;
+
+/// Source/destination addresses recovered from a PROXY protocol header.
+#[derive(Debug, Clone, Copy)]
+pub struct ProxyProtocolHeader {
+    pub source: SocketAddr,
+    pub destination: SocketAddr,
+}
+
+fn invalid(msg: &str) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, format!("malformed PROXY header: {msg}"))
+}
+
+/// How many peeks to make while the probe is still short; after that the
+/// connection is classified by whatever bytes have arrived so far.
+const PEEK_RETRY_ATTEMPTS: usize = 20;
+const PEEK_RETRY_DELAY: std::time::Duration = std::time::Duration::from_millis(5);
+
+/// Retries `TcpStream::peek` until at least `want` bytes are available, the
+/// stream is closed, or `PEEK_RETRY_ATTEMPTS` is exhausted. A real load
+/// balancer can split its PROXY header across TCP segments, so a single peek
+/// can come up short even though the rest is about to arrive; peeking (rather
+/// than reading) keeps those bytes available for the v1/v2 parsers, or for
+/// the plain-HTTP fallback case, to read for real afterwards.
+async fn peek_until(stream: &mut TcpStream, want: usize) -> io::Result<Vec<u8>> {
+    let mut probe = vec![0u8; want];
+    let mut peeked = 0;
+
+    for _ in 0..PEEK_RETRY_ATTEMPTS {
+        peeked = stream.peek(&mut probe).await?;
+        if peeked >= want || peeked == 0 {
+            break;
+        }
+        tokio::time::sleep(PEEK_RETRY_DELAY).await;
+    }
+
+    probe.truncate(peeked);
+    Ok(probe)
+}
+
+/// Reads and decodes a PROXY protocol header off the front of `stream`, consuming
+/// exactly its bytes. Returns `Ok(None)` when the connection carries no usable
+/// address (a v1 `UNKNOWN` proto or a v2 `LOCAL` command, e.g. a load balancer
+/// health check) or when the stream doesn't start with a PROXY header at all, in
+/// which case the caller should fall back to the raw peer address.
+pub async fn read_proxy_protocol_header(
+    stream: &mut TcpStream,
+) -> io::Result<Option<ProxyProtocolHeader>> {
+    let probe = peek_until(stream, V2_SIGNATURE.len()).await?;
+
+    if probe.len() >= V2_SIGNATURE.len() && probe[..V2_SIGNATURE.len()] == V2_SIGNATURE {
+        return read_v2_header(stream).await;
+    }
+
+    if probe.len() >= 6 && &probe[..6] == b"PROXY " {
+        return read_v1_header(stream).await;
+    }
+
+    Ok(None)
+}
+
+async fn read_v1_header(stream: &mut TcpStream) -> io::Result<Option<ProxyProtocolHeader>> {
+    let mut line = Vec::with_capacity(32);
+    let mut byte = [0u8; 1];
+    loop {
+        stream.read_exact(&mut byte).await?;
+        line.push(byte[0]);
+        if line.len() > MAX_V1_LINE_LEN {
+            return Err(invalid("v1 header exceeds 107 bytes"));
+        }
+        if line.ends_with(b"\r\n") {
+            break;
+        }
+    }
+
+    let text = std::str::from_utf8(&line)
+        .map_err(|_| invalid("v1 header is not valid UTF-8"))?
+        .trim_end();
+    let mut parts = text.split_whitespace();
+
+    match (parts.next(), parts.next()) {
+        (Some("PROXY"), Some("UNKNOWN")) => Ok(None),
+        (Some("PROXY"), Some("TCP4")) | (Some("PROXY"), Some("TCP6")) => {
+            let src_ip = parts.next().ok_or_else(|| invalid("missing source address"))?;
+            let dst_ip = parts
+                .next()
+                .ok_or_else(|| invalid("missing destination address"))?;
+            let src_port: u16 = parts
+                .next()
+                .ok_or_else(|| invalid("missing source port"))?
+                .parse()
+                .map_err(|_| invalid("bad source port"))?;
+            let dst_port: u16 = parts
+                .next()
+                .ok_or_else(|| invalid("missing destination port"))?
+                .parse()
+                .map_err(|_| invalid("bad destination port"))?;
+
+            Ok(Some(ProxyProtocolHeader {
+                source: SocketAddr::new(
+                    src_ip.parse().map_err(|_| invalid("bad source ip"))?,
+                    src_port,
+                ),
+                destination: SocketAddr::new(
+                    dst_ip.parse().map_err(|_| invalid("bad destination ip"))?,
+                    dst_port,
+                ),
+            }))
+        }
+        _ => Err(invalid("unrecognized v1 header")),
+    }
+}
+
+async fn read_v2_header(stream: &mut TcpStream) -> io::Result<Option<ProxyProtocolHeader>> {
+    // 12-byte signature + 1 version/command byte + 1 family/protocol byte + 2-byte length
+    let mut fixed = [0u8; 16];
+    stream.read_exact(&mut fixed).await?;
+
+    let version = fixed[12] >> 4;
+    let command = fixed[12] & 0x0F;
+    if version != 2 {
+        return Err(invalid("unsupported v2 version"));
+    }
+
+    let family = fixed[13] >> 4;
+    let len = u16::from_be_bytes([fixed[14], fixed[15]]) as usize;
+
+    let mut address_block = vec![0u8; len];
+    stream.read_exact(&mut address_block).await?;
+
+    // LOCAL connections (e.g. load balancer health checks) carry no client address.
+    if command == 0x0 {
+        return Ok(None);
+    }
+
+    match family {
+        // AF_INET: 4-byte source addr, 4-byte dest addr, 2-byte source port, 2-byte dest port
+        0x1 if address_block.len() >= 12 => {
+            let src_port = u16::from_be_bytes([address_block[8], address_block[9]]);
+            let dst_port = u16::from_be_bytes([address_block[10], address_block[11]]);
+            Ok(Some(ProxyProtocolHeader {
+                source: SocketAddr::new(
+                    Ipv4Addr::new(
+                        address_block[0],
+                        address_block[1],
+                        address_block[2],
+                        address_block[3],
+                    )
+                    .into(),
+                    src_port,
+                ),
+                destination: SocketAddr::new(
+                    Ipv4Addr::new(
+                        address_block[4],
+                        address_block[5],
+                        address_block[6],
+                        address_block[7],
+                    )
+                    .into(),
+                    dst_port,
+                ),
+            }))
+        }
+        // AF_INET6: 16-byte source addr, 16-byte dest addr, 2-byte source port, 2-byte dest port
+        0x2 if address_block.len() >= 36 => {
+            let mut src_octets = [0u8; 16];
+            src_octets.copy_from_slice(&address_block[0..16]);
+            let mut dst_octets = [0u8; 16];
+            dst_octets.copy_from_slice(&address_block[16..32]);
+            let src_port = u16::from_be_bytes([address_block[32], address_block[33]]);
+            let dst_port = u16::from_be_bytes([address_block[34], address_block[35]]);
+            Ok(Some(ProxyProtocolHeader {
+                source: SocketAddr::new(Ipv6Addr::from(src_octets).into(), src_port),
+                destination: SocketAddr::new(Ipv6Addr::from(dst_octets).into(), dst_port),
+            }))
+        }
+        // AF_UNSPEC / AF_UNIX / truncated block: no routable address to recover.
+        _ => Ok(None),
+    }
+}
@@ -0,0 +1,312 @@
+use std::collections::HashMap;
+use std::net::SocketAddr;
+use std::sync::Arc;
+
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::TcpStream;
+use tokio::sync::Mutex;
+
+use crate::config::{ListenerConfig, Upstream};
+
+/// Caches resolved upstream addresses so we don't re-resolve on every
+/// connection; on a dial failure the address's entry is cleared, re-resolved
+/// and the dial retried once within the same connection.
+type ResolvedAddrs = Arc<Mutex<HashMap<String, SocketAddr>>>;
+
+/// Runs a single configured listener forever, spawning a task per connection.
+pub async fn run_listener(config: ListenerConfig) -> std::io::Result<()> {
+    let listener = tokio::net::TcpListener::bind(&config.bind).await?;
+    println!("🔀 L4 router listening on {}", config.bind);
+
+    let resolved: ResolvedAddrs = Arc::new(Mutex::new(HashMap::new()));
+    let config = Arc::new(config);
+
+    loop {
+        let (stream, peer_addr) = match listener.accept().await {
+            Ok(accepted) => accepted,
+            Err(err) => {
+                eprintln!("⚠️  Failed to accept connection on {}: {err}", config.bind);
+                continue;
+            }
+        };
+        let config = config.clone();
+        let resolved = resolved.clone();
+
+        tokio::spawn(async move {
+            if let Err(err) = handle_connection(stream, &config, &resolved).await {
+                eprintln!("⚠️  L4 router error for {peer_addr}: {err}");
+            }
+        });
+    }
+}
+
+async fn handle_connection(
+    mut stream: TcpStream,
+    config: &ListenerConfig,
+    resolved: &ResolvedAddrs,
+) -> std::io::Result<()> {
+    // Bytes already consumed off the stream while sniffing the SNI, which need
+    // to be replayed to whichever upstream we end up picking.
+    let mut replay = Vec::new();
+
+    let upstream = if config.tls {
+        let probe = read_client_hello_probe(&mut stream).await?;
+        let hostname = sni_hostname_from_client_hello(&probe);
+        replay = probe;
+
+        match hostname.and_then(|h| config.sni.get(&h)) {
+            Some(upstream) => upstream,
+            None => &config.default,
+        }
+    } else {
+        &config.default
+    };
+
+    match upstream {
+        Upstream::Ban => Ok(()),
+        Upstream::Echo => run_echo(stream, replay).await,
+        Upstream::Proxy {
+            addr,
+            protocol,
+            kcp,
+        } => run_proxy(stream, replay, addr, protocol, kcp, resolved).await,
+    }
+}
+
+async fn run_echo(mut stream: TcpStream, replay: Vec<u8>) -> std::io::Result<()> {
+    if !replay.is_empty() {
+        stream.write_all(&replay).await?;
+    }
+
+    let mut buf = [0u8; 4096];
+    loop {
+        let n = stream.read(&mut buf).await?;
+        if n == 0 {
+            return Ok(());
+        }
+        stream.write_all(&buf[..n]).await?;
+    }
+}
+
+/// Dials `addr` (over TCP or KCP, per `protocol`) and splices it to `client`.
+///
+/// This is a raw TCP/KCP splice with no HTTP response to attach a status to,
+/// so dial/timeout failures can't literally be "surfaced through `ProxyError`
+/// mapped to 502" the way the HTTP reverse proxy in `proxy.rs` does — there's
+/// no response in flight by the time we know the upstream is unreachable.
+/// Instead, failures are returned as `io::Error` with the upstream address and
+/// protocol folded into the message, so `run_listener`'s log line identifies
+/// which upstream failed rather than just printing a bare OS error.
+async fn run_proxy(
+    mut client: TcpStream,
+    replay: Vec<u8>,
+    addr: &str,
+    protocol: &str,
+    kcp: &crate::config::KcpConfig,
+    resolved: &ResolvedAddrs,
+) -> std::io::Result<()> {
+    match protocol {
+        "tcp" => {
+            let mut backend = match dial_resolved(addr, resolved).await {
+                Ok(backend) => backend,
+                Err(_) => {
+                    // Stale cached address (e.g. the upstream moved) — re-resolve once.
+                    resolved.lock().await.remove(addr);
+                    dial_resolved(addr, resolved)
+                        .await
+                        .map_err(|e| dial_context(addr, protocol, e))?
+                }
+            };
+
+            if !replay.is_empty() {
+                backend.write_all(&replay).await?;
+            }
+
+            tokio::io::copy_bidirectional(&mut client, &mut backend).await?;
+            Ok(())
+        }
+        "kcp" => {
+            let mut backend = match dial_resolved_kcp(addr, kcp, resolved).await {
+                Ok(backend) => backend,
+                Err(_) => {
+                    // Stale cached address (e.g. the upstream moved) — re-resolve once.
+                    resolved.lock().await.remove(addr);
+                    dial_resolved_kcp(addr, kcp, resolved)
+                        .await
+                        .map_err(|e| dial_context(addr, protocol, e))?
+                }
+            };
+
+            if !replay.is_empty() {
+                backend.write_all(&replay).await?;
+            }
+
+            tokio::io::copy_bidirectional(&mut client, &mut backend).await?;
+            Ok(())
+        }
+        other => Err(std::io::Error::new(
+            std::io::ErrorKind::Unsupported,
+            format!("unsupported upstream protocol: {other}"),
+        )),
+    }
+}
+
+fn dial_context(addr: &str, protocol: &str, err: std::io::Error) -> std::io::Error {
+    std::io::Error::new(
+        err.kind(),
+        format!("failed to reach {protocol} upstream {addr}: {err}"),
+    )
+}
+
+fn kcp_config(config: &crate::config::KcpConfig) -> tokio_kcp::KcpConfig {
+    tokio_kcp::KcpConfig {
+        mtu: config.mtu,
+        nodelay: tokio_kcp::KcpNoDelayConfig {
+            nodelay: config.nodelay,
+            interval: config.interval as i32,
+            resend: config.resend as i32,
+            nc: true,
+        },
+        wnd_size: (config.flow_control_window, config.flow_control_window),
+        session_expire: std::time::Duration::from_secs(90),
+        flush_write: false,
+        flush_acks_input: false,
+        stream: true,
+    }
+}
+
+async fn dial_resolved(addr: &str, resolved: &ResolvedAddrs) -> std::io::Result<TcpStream> {
+    let socket_addr = dial_resolved_addr(addr, resolved).await?;
+    TcpStream::connect(socket_addr).await
+}
+
+async fn dial_resolved_kcp(
+    addr: &str,
+    kcp: &crate::config::KcpConfig,
+    resolved: &ResolvedAddrs,
+) -> std::io::Result<tokio_kcp::KcpStream> {
+    let socket_addr = dial_resolved_addr(addr, resolved).await?;
+    tokio_kcp::KcpStream::connect(&kcp_config(kcp), socket_addr)
+        .await
+        .map_err(|e| std::io::Error::new(std::io::ErrorKind::Other, e))
+}
+
+async fn dial_resolved_addr(addr: &str, resolved: &ResolvedAddrs) -> std::io::Result<SocketAddr> {
+    let cached = resolved.lock().await.get(addr).copied();
+    if let Some(socket_addr) = cached {
+        return Ok(socket_addr);
+    }
+
+    let socket_addr = tokio::net::lookup_host(addr)
+        .await?
+        .next()
+        .ok_or_else(|| {
+            std::io::Error::new(
+                std::io::ErrorKind::NotFound,
+                format!("no address found for upstream {addr}"),
+            )
+        })?;
+    resolved.lock().await.insert(addr.to_string(), socket_addr);
+    Ok(socket_addr)
+}
+
+/// Largest ClientHello we'll buffer while sniffing SNI — a full max-size TLS
+/// record plus its 5-byte header.
+const MAX_CLIENT_HELLO_PROBE: usize = 16 * 1024 + 5;
+
+/// Reads off `stream` until a full TLS record is buffered (per the record
+/// length in bytes `[3..5]`) or `MAX_CLIENT_HELLO_PROBE` is hit, since a
+/// ClientHello with many extensions routinely spans more than one `read`.
+/// Whatever is read is returned for both SNI parsing and replay to the
+/// chosen upstream.
+async fn read_client_hello_probe(stream: &mut TcpStream) -> std::io::Result<Vec<u8>> {
+    let mut buf = Vec::with_capacity(4096);
+    let mut chunk = [0u8; 4096];
+
+    loop {
+        let n = stream.read(&mut chunk).await?;
+        if n == 0 {
+            break;
+        }
+        buf.extend_from_slice(&chunk[..n]);
+
+        if buf.len() >= 5 {
+            let record_len = u16::from_be_bytes([buf[3], buf[4]]) as usize;
+            if buf.len() >= 5 + record_len {
+                break;
+            }
+        }
+
+        if buf.len() >= MAX_CLIENT_HELLO_PROBE {
+            break;
+        }
+    }
+
+    Ok(buf)
+}
+
+/// Parses just enough of a TLS ClientHello to pull out the SNI hostname,
+/// without terminating TLS. Returns `None` on anything unexpected — a short
+/// read, a non-handshake record, or a missing SNI extension.
+fn sni_hostname_from_client_hello(data: &[u8]) -> Option<String> {
+    // TLS record header: content type (0x16 = handshake), version (2 bytes), length (2 bytes)
+    if data.len() < 5 || data[0] != 0x16 {
+        return None;
+    }
+    let record = &data[5..];
+
+    // Handshake header: msg type (0x01 = client_hello), 3-byte length
+    if record.len() < 4 || record[0] != 0x01 {
+        return None;
+    }
+    let mut pos = 4;
+
+    // Client version (2) + random (32)
+    pos = pos.checked_add(34)?;
+    let session_id_len = *record.get(pos)? as usize;
+    pos = pos.checked_add(1)?.checked_add(session_id_len)?;
+
+    let cipher_suites_len = u16::from_be_bytes([*record.get(pos)?, *record.get(pos + 1)?]) as usize;
+    pos = pos.checked_add(2)?.checked_add(cipher_suites_len)?;
+
+    let compression_methods_len = *record.get(pos)? as usize;
+    pos = pos.checked_add(1)?.checked_add(compression_methods_len)?;
+
+    let extensions_len = u16::from_be_bytes([*record.get(pos)?, *record.get(pos + 1)?]) as usize;
+    pos = pos.checked_add(2)?;
+    let extensions_end = pos.checked_add(extensions_len)?;
+    let extensions = record.get(pos..extensions_end)?;
+
+    let mut cursor = 0;
+    while cursor + 4 <= extensions.len() {
+        let ext_type = u16::from_be_bytes([extensions[cursor], extensions[cursor + 1]]);
+        let ext_len =
+            u16::from_be_bytes([extensions[cursor + 2], extensions[cursor + 3]]) as usize;
+        let ext_data = extensions.get(cursor + 4..cursor + 4 + ext_len)?;
+
+        // server_name extension
+        if ext_type == 0x0000 {
+            // server_name_list length (2) + entries of [type(1), length(2), name]
+            let list = ext_data.get(2..)?;
+            let mut list_cursor = 0;
+            while list_cursor + 3 <= list.len() {
+                let name_type = list[list_cursor];
+                let name_len = u16::from_be_bytes([
+                    list[list_cursor + 1],
+                    list[list_cursor + 2],
+                ]) as usize;
+                let name = list.get(list_cursor + 3..list_cursor + 3 + name_len)?;
+
+                // host_name
+                if name_type == 0x00 {
+                    return std::str::from_utf8(name).ok().map(str::to_string);
+                }
+                list_cursor += 3 + name_len;
+            }
+        }
+
+        cursor += 4 + ext_len;
+    }
+
+    None
+}
@@ -20,7 +20,7 @@ pub async fn input() -> String{
     inp.trim().to_string()
 }
 
-async fn chat(client : &mut ChatServiceClient<Channel>){
+async fn chat(client : &mut ChatServiceClient<Channel>, room: String){
     let (tx, rx) = mpsc::channel(128);
     let mut in_stream = ReceiverStream::new(rx);
 
@@ -34,6 +34,7 @@ async fn chat(client : &mut ChatServiceClient<Channel>){
                 let msg = ChatMessage {
                     message: user_msg,
                     from: "Client".to_string(),
+                    room: room.clone(),
                 };
 
                 if tx.send(msg).await.is_err() {
@@ -69,7 +70,10 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
     let mut client = ChatServiceClient::connect("http://[::1]:50051").await.unwrap();
     println!("Connected to chat server...");
 
-    chat(&mut client).await;
+    let room = std::env::args().nth(1).unwrap_or_else(|| "general".to_string());
+    println!("Joining room: {room}");
+
+    chat(&mut client, room).await;
 
     Ok(())
 }
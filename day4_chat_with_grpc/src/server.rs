@@ -3,16 +3,26 @@ pub mod pb {
 }
 
 use pb::ChatMessage;
+use std::collections::HashMap;
 use std::net::ToSocketAddrs;
 use std::pin::Pin;
-use tokio::sync::mpsc;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Arc;
+use tokio::sync::{mpsc, RwLock};
 use tokio_stream::{Stream, StreamExt};
 use tokio_stream::wrappers::ReceiverStream;
 use tonic::transport::Server;
 use tonic::{Request, Response, Status, Streaming};
 
-#[derive(Debug)]
-pub struct ChatServer {}
+type ClientId = u64;
+type ClientSender = mpsc::Sender<Result<ChatMessage, Status>>;
+type Rooms = Arc<RwLock<HashMap<String, HashMap<ClientId, ClientSender>>>>;
+
+#[derive(Debug, Default)]
+pub struct ChatServer {
+    rooms: Rooms,
+    next_client_id: AtomicU64,
+}
 
 type ResponseStream = Pin<Box<dyn Stream<Item = Result<ChatMessage, Status>> + Send>>;
 type ChatResult<T> = Result<Response<T>, Status>;
@@ -26,18 +36,42 @@ impl pb::chat_service_server::ChatService for ChatServer {
     ) -> ChatResult<Self::ChatMessageStreamingStream> {
         let mut in_stream = request.into_inner();
         let (tx, rx) = mpsc::channel(128);
+        let client_id = self.next_client_id.fetch_add(1, Ordering::Relaxed);
+        let rooms = self.rooms.clone();
 
         tokio::spawn(async move {
+            let mut joined_room: Option<String> = None;
+
             while let Some(result) = in_stream.next().await {
                 match result {
                     Ok(item) => {
-                        println!("Received message: {:?} from {:?}", item.message, item.from);
-                        tx.send(Ok(ChatMessage {
-                            message: format!("Server : {}", item.message),
-                            from: "Server".to_string(),
-                        }))
-                        .await
-                        .unwrap();
+                        println!(
+                            "Received message: {:?} from {:?} in room {:?}",
+                            item.message, item.from, item.room
+                        );
+
+                        // Register this client's sender under its room on the first message.
+                        if joined_room.is_none() {
+                            joined_room = Some(item.room.clone());
+                            rooms
+                                .write()
+                                .await
+                                .entry(item.room.clone())
+                                .or_default()
+                                .insert(client_id, tx.clone());
+                        }
+
+                        // Fan out to everyone currently in the room, including the sender, pruning
+                        // closed channels (a momentarily full channel is left in place, not evicted).
+                        let mut members = rooms.write().await;
+                        if let Some(senders) = members.get_mut(&item.room) {
+                            senders.retain(|_, sender| {
+                                match sender.try_send(Ok(item.clone())) {
+                                    Ok(()) | Err(mpsc::error::TrySendError::Full(_)) => true,
+                                    Err(mpsc::error::TrySendError::Closed(_)) => false,
+                                }
+                            });
+                        }
                     }
                     Err(status) => {
                         println!("Error receiving message: {:?}", status);
@@ -46,6 +80,17 @@ impl pb::chat_service_server::ChatService for ChatServer {
                 }
             }
 
+            // Deregister the client, dropping the room entry if it's now empty.
+            if let Some(room) = joined_room {
+                let mut members = rooms.write().await;
+                if let Some(senders) = members.get_mut(&room) {
+                    senders.remove(&client_id);
+                    if senders.is_empty() {
+                        members.remove(&room);
+                    }
+                }
+            }
+
             println!("Chat session ended...");
         });
 
@@ -58,7 +103,7 @@ impl pb::chat_service_server::ChatService for ChatServer {
 
 #[tokio::main]
 async fn main() -> Result<(), Box<dyn std::error::Error>> {
-    let server = ChatServer {};
+    let server = ChatServer::default();
     println!("Starting gRPC chat server...");
     Server::builder()
         .add_service(pb::chat_service_server::ChatServiceServer::new(server))
@@ -67,4 +112,4 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
         .unwrap();
 
     Ok(())
-}
\ No newline at end of file
+}